@@ -2,6 +2,8 @@ use std::env::var_os;
 use std::fs::File;
 use std::path::PathBuf;
 use reqwest::header::USER_AGENT;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
 use tokio::fs;
 use crate::{CLIENT, DEFAULT_AGENT};
 
@@ -35,14 +37,32 @@ pub async fn create_dir(path: &str) {
     fs::create_dir(path).await.unwrap();
 }
 
-/// Gets the path to the device's AppData directory.
-pub fn get_appdata() -> Option<PathBuf> {
+/// Gets the path to the device's AppData directory. Windows-only.
+fn get_appdata() -> Option<PathBuf> {
     var_os("APPDATA").map(PathBuf::from)
 }
 
+/// Gets the path to the user's home directory.
+fn get_home() -> Option<PathBuf> {
+    var_os("HOME").map(PathBuf::from)
+}
+
 /// Gets the path to the device's temporary directory.
-pub fn get_temp() -> Option<PathBuf> {
-    var_os("TEMP").map(PathBuf::from)
+pub fn get_temp() -> PathBuf {
+    std::env::temp_dir()
+}
+
+/// Gets the path to the `.minecraft` directory, appropriate for the current OS:
+/// `%APPDATA%/.minecraft` on Windows, `~/Library/Application Support/minecraft` on macOS,
+/// and `~/.minecraft` on Linux.
+pub fn get_minecraft_dir() -> PathBuf {
+    if cfg!(target_os = "windows") {
+        get_appdata().expect("APPDATA is not set.").join(".minecraft")
+    } else if cfg!(target_os = "macos") {
+        get_home().expect("HOME is not set.").join("Library").join("Application Support").join("minecraft")
+    } else {
+        get_home().expect("HOME is not set.").join(".minecraft")
+    }
 }
 
 /// Downloads a file from the internet.
@@ -53,6 +73,43 @@ pub async fn download(url: String, path: String) -> Result<(), reqwest::Error> {
     Ok(fs::write(path, bytes).await.unwrap())
 }
 
+/// Computes the SHA-1 hash of a file's contents, as a lowercase hex string.
+pub async fn sha1(path: &str) -> String {
+    let bytes = fs::read(path).await.unwrap();
+    hex::encode(Sha1::digest(&bytes))
+}
+
+/// Computes the SHA-256 hash of a file's contents, as a lowercase hex string.
+pub async fn sha256(path: &str) -> String {
+    let bytes = fs::read(path).await.unwrap();
+    hex::encode(Sha256::digest(&bytes))
+}
+
+/// Computes the SHA-512 hash of a file's contents, as a lowercase hex string.
+pub async fn sha512(path: &str) -> String {
+    let bytes = fs::read(path).await.unwrap();
+    hex::encode(Sha512::digest(&bytes))
+}
+
+/// Checks a file's digest against an expected hash, computed with the named algorithm
+/// (`sha1`, `sha256`, or `sha512`; unrecognized or missing algorithms fall back to `sha1`,
+/// matching what CurseForge/Modrinth already report). Files with no expected hash are
+/// trusted once downloaded.
+pub async fn matches_hash(path: &str, hash: &Option<String>, algo: &Option<String>) -> bool {
+    let expected = match hash {
+        Some(expected) => expected,
+        None => return true
+    };
+
+    let actual = match algo.as_deref() {
+        Some("sha256") => sha256(path).await,
+        Some("sha512") => sha512(path).await,
+        _ => sha1(path).await
+    };
+
+    &actual == expected
+}
+
 /// Checks if the URL is valid.
 pub fn is_url(url: String) -> bool {
     url.starts_with("http://") || url.starts_with("https://")
@@ -0,0 +1,32 @@
+pub mod mrpack;
+pub mod packwiz;
+
+/// Converts a pack's display name into a safe instance folder name.
+pub(crate) fn sanitize_folder(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+        .collect()
+}
+
+/// Strips path-traversal and rooted components from an externally-sourced relative path
+/// (a zip entry name, a `modrinth.index.json` file path, a packwiz filename), so a crafted
+/// pack can't write outside the instance directory it's extracted or downloaded into.
+pub(crate) fn sanitize_relative_path(path: &str) -> String {
+    path.split(['/', '\\'])
+        .filter(|segment| !segment.is_empty() && *segment != "." && *segment != "..")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Maps a detected `loader_type` and its raw loader version into this installer's `loader`
+/// (the version-id folder checked for under `.minecraft/versions`) and `fabric` (the
+/// loader-version string `download_loader`'s installers expect) descriptor fields, mirroring
+/// the version-id conventions each loader's own installer produces.
+pub(crate) fn describe_loader(loader_type: &str, loader_version: &str, minecraft: &str) -> (String, String) {
+    match loader_type {
+        "quilt" => (format!("quilt-loader-{}-{}", loader_version, minecraft), loader_version.to_string()),
+        "forge" => (format!("{}-forge-{}", minecraft, loader_version), format!("{}-{}", minecraft, loader_version)),
+        "neoforge" => (format!("neoforge-{}", loader_version), format!("{}-{}", minecraft, loader_version)),
+        _ => (format!("fabric-loader-{}-{}", loader_version, minecraft), loader_version.to_string())
+    }
+}
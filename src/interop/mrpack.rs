@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+
+use serde::Deserialize;
+use zip::ZipArchive;
+
+use crate::interop::{describe_loader, sanitize_folder, sanitize_relative_path};
+use crate::{default_concurrency, default_loader_type, External, ModPackDescriptor};
+
+#[derive(Deserialize)]
+struct MrpackIndex {
+    name: String,
+    #[serde(rename = "versionId")]
+    version_id: String,
+    #[serde(default)]
+    dependencies: HashMap<String, String>,
+    files: Vec<MrpackFile>
+}
+
+#[derive(Deserialize)]
+struct MrpackFile {
+    path: String,
+    downloads: Vec<String>,
+    #[serde(default)]
+    hashes: Option<MrpackFileHashes>
+}
+
+#[derive(Deserialize)]
+struct MrpackFileHashes {
+    sha1: Option<String>,
+    sha512: Option<String>
+}
+
+/// Picks the strongest hash a `modrinth.index.json` file entry reported, paired with the
+/// algorithm name `files::matches_hash` expects.
+fn pick_hash(hashes: &Option<MrpackFileHashes>) -> (Option<String>, Option<String>) {
+    match hashes {
+        Some(MrpackFileHashes { sha512: Some(sha512), .. }) => (Some(sha512.clone()), Some("sha512".to_string())),
+        Some(MrpackFileHashes { sha1: Some(sha1), .. }) => (Some(sha1.clone()), Some("sha1".to_string())),
+        _ => (None, None)
+    }
+}
+
+/// Reads a `.mrpack` archive and builds an equivalent `ModPackDescriptor`.
+/// Every indexed file with at least one download URL becomes an `External` entry (using its
+/// first URL) so the existing install flow downloads it; files with no URL are left out;
+/// version-picking isn't needed here since `.mrpack` already pins concrete URLs.
+pub fn load(archive_path: &str) -> ModPackDescriptor {
+    let file = File::open(archive_path).unwrap();
+    let mut archive = ZipArchive::new(file).unwrap();
+
+    let index: MrpackIndex = {
+        let mut entry = archive.by_name("modrinth.index.json").unwrap();
+        let mut contents = String::new();
+        entry.read_to_string(&mut contents).unwrap();
+        serde_json::from_str(&contents).unwrap()
+    };
+
+    let external = index.files.into_iter()
+        .filter(|file| !file.downloads.is_empty())
+        .map(|file| {
+            let (hash, hash_algo) = pick_hash(&file.hashes);
+            External {
+                url: file.downloads[0].clone(),
+                file: sanitize_relative_path(&file.path),
+                extract: None,
+                hash,
+                hash_algo
+            }
+        })
+        .collect();
+
+    let minecraft = index.dependencies.get("minecraft").cloned().unwrap_or_default();
+    let (loader_type, loader_version) = if let Some(version) = index.dependencies.get("quilt-loader") {
+        ("quilt".to_string(), version.clone())
+    } else if let Some(version) = index.dependencies.get("forge") {
+        ("forge".to_string(), version.clone())
+    } else if let Some(version) = index.dependencies.get("neoforge") {
+        ("neoforge".to_string(), version.clone())
+    } else if let Some(version) = index.dependencies.get("fabric-loader") {
+        ("fabric".to_string(), version.clone())
+    } else {
+        (default_loader_type(), String::new())
+    };
+    let (loader, fabric) = describe_loader(&loader_type, &loader_version, &minecraft);
+
+    ModPackDescriptor {
+        name: index.name.clone(),
+        version: index.version_id,
+        loader,
+        folder: sanitize_folder(&index.name),
+        target: minecraft,
+        fabric,
+        concurrency: default_concurrency(),
+        loader_type,
+        mods: vec![],
+        external
+    }
+}
+
+/// Extracts the `overrides/` folder of a `.mrpack` archive into the instance directory.
+pub fn extract_overrides(archive_path: &str, destination: &str) {
+    let file = File::open(archive_path).unwrap();
+    let mut archive = ZipArchive::new(file).unwrap();
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).unwrap();
+        let relative = match entry.name().strip_prefix("overrides/") {
+            Some(relative) if !relative.is_empty() => sanitize_relative_path(relative),
+            _ => continue
+        };
+        if entry.is_dir() || relative.is_empty() {
+            continue;
+        }
+
+        let target_path = format!("{}/{}", destination, relative);
+        if let Some(parent) = std::path::Path::new(&target_path).parent() {
+            std::fs::create_dir_all(parent).unwrap();
+        }
+
+        let mut out = File::create(&target_path).unwrap();
+        std::io::copy(&mut entry, &mut out).unwrap();
+    }
+}
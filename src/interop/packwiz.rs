@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+
+use reqwest::header::USER_AGENT;
+use serde::Deserialize;
+
+use crate::interop::{describe_loader, sanitize_folder, sanitize_relative_path};
+use crate::{default_concurrency, default_loader_type, CLIENT, DEFAULT_AGENT, External, ModPackDescriptor};
+
+#[derive(Deserialize)]
+struct PackToml {
+    name: String,
+    versions: HashMap<String, String>,
+    index: PackIndexRef
+}
+
+#[derive(Deserialize)]
+struct PackIndexRef {
+    file: String
+}
+
+#[derive(Deserialize)]
+struct PackIndex {
+    files: Vec<PackIndexFile>
+}
+
+#[derive(Deserialize)]
+struct PackIndexFile {
+    file: String
+}
+
+#[derive(Deserialize)]
+struct PwToml {
+    filename: String,
+    #[serde(default)]
+    hash: Option<String>,
+    #[serde(default, rename = "hash-format")]
+    hash_format: Option<String>,
+    download: Option<PwDownload>,
+    update: Option<PwUpdate>
+}
+
+#[derive(Deserialize)]
+struct PwDownload {
+    url: String
+}
+
+#[derive(Deserialize)]
+struct PwUpdate {
+    modrinth: Option<PwUpdateModrinth>,
+    curseforge: Option<PwUpdateCurseforge>
+}
+
+#[derive(Deserialize)]
+struct PwUpdateModrinth {
+    #[serde(rename = "mod-id")]
+    mod_id: String
+}
+
+#[derive(Deserialize)]
+struct PwUpdateCurseforge {
+    #[serde(rename = "project-id")]
+    project_id: u32
+}
+
+/// Imports a packwiz modpack from a `pack.toml` URL, resolving every `.pw.toml` entry
+/// in its file index into either a direct-download `External` (if it has a `download.url`)
+/// or a scheme-prefixed `mods` entry resolved through the pluggable sources (if it only has
+/// a Modrinth/CurseForge `update` block).
+pub async fn load(pack_toml_url: &str) -> ModPackDescriptor {
+    let base = pack_toml_url.rsplit_once('/').map(|(base, _)| base).unwrap_or("");
+
+    let pack_toml: PackToml = toml::from_str(&fetch(pack_toml_url).await).unwrap();
+    let index: PackIndex = toml::from_str(&fetch(&format!("{}/{}", base, pack_toml.index.file)).await).unwrap();
+
+    let mut external = Vec::new();
+    let mut mods = Vec::new();
+    for entry in index.files {
+        let pw: PwToml = toml::from_str(&fetch(&format!("{}/{}", base, entry.file)).await).unwrap();
+
+        if let Some(download) = pw.download {
+            // Only the hash algorithms `files::matches_hash` knows how to verify are worth
+            // keeping; anything else (e.g. packwiz's `md5` option) is left unverified.
+            let (hash, hash_algo) = match pw.hash_format.as_deref() {
+                Some("sha1") | Some("sha256") | Some("sha512") => (pw.hash.clone(), pw.hash_format.clone()),
+                _ => (None, None)
+            };
+            external.push(External {
+                url: download.url,
+                file: format!("mods/{}", sanitize_relative_path(&pw.filename)),
+                extract: None,
+                hash,
+                hash_algo
+            });
+            continue;
+        }
+
+        // No direct download; resolve the Modrinth/CurseForge update block through the
+        // same pluggable sources the native descriptor format uses.
+        if let Some(update) = pw.update {
+            if let Some(modrinth) = update.modrinth {
+                mods.push(format!("modrinth:{}", modrinth.mod_id));
+                continue;
+            }
+            if let Some(curseforge) = update.curseforge {
+                mods.push(format!("curseforge:{}", curseforge.project_id));
+                continue;
+            }
+        }
+
+        println!("Skipping {}: no direct download URL or resolvable update block.", pw.filename);
+    }
+
+    let minecraft = pack_toml.versions.get("minecraft").cloned().unwrap_or_default();
+    let (loader_type, loader_version) = if let Some(version) = pack_toml.versions.get("quilt") {
+        ("quilt".to_string(), version.clone())
+    } else if let Some(version) = pack_toml.versions.get("forge") {
+        ("forge".to_string(), version.clone())
+    } else if let Some(version) = pack_toml.versions.get("neoforge") {
+        ("neoforge".to_string(), version.clone())
+    } else if let Some(version) = pack_toml.versions.get("fabric") {
+        ("fabric".to_string(), version.clone())
+    } else {
+        (default_loader_type(), String::new())
+    };
+    let (loader, fabric) = describe_loader(&loader_type, &loader_version, &minecraft);
+
+    ModPackDescriptor {
+        name: pack_toml.name.clone(),
+        version: minecraft.clone(),
+        loader,
+        folder: sanitize_folder(&pack_toml.name),
+        target: minecraft,
+        fabric,
+        concurrency: default_concurrency(),
+        loader_type,
+        mods,
+        external
+    }
+}
+
+async fn fetch(url: &str) -> String {
+    CLIENT.get(url)
+        .header(USER_AGENT, DEFAULT_AGENT.clone())
+        .send().await.unwrap()
+        .text().await.unwrap()
+}
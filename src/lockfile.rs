@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+
+use crate::files;
+
+const LOCKFILE_NAME: &str = "modpack.lock.json";
+
+/// A single resolved file, recorded so a later run can tell it apart from a fresh entry,
+/// or notice that the source re-resolved to a different version of the same entry.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LockedMod {
+    pub source_id: String,
+    pub file_path: String,
+    pub sha1: Option<String>,
+    pub version: Option<String>
+}
+
+/// Records every file the installer resolved for an instance - both `mods` entries
+/// (keyed by their scheme-prefixed source ID) and `external` entries (keyed by URL, since
+/// they carry no other stable ID). Written after install so a later run can diff against
+/// it instead of treating the instance folder's mere existence as "already installed".
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Lockfile {
+    pub mods: Vec<LockedMod>,
+    pub external: Vec<LockedMod>
+}
+
+impl Lockfile {
+    pub fn empty() -> Lockfile {
+        Lockfile { mods: vec![], external: vec![] }
+    }
+}
+
+/// Reads the lockfile from the instance directory, or an empty one if none exists yet.
+pub async fn read(modpack_dir: &str) -> Lockfile {
+    let path = format!("{}/{}", modpack_dir, LOCKFILE_NAME);
+    if !files::exists(&path).await {
+        return Lockfile::empty();
+    }
+
+    serde_json::from_str(&files::read(&path).await).unwrap_or_else(|_| Lockfile::empty())
+}
+
+/// Writes the lockfile into the instance directory.
+pub async fn write(modpack_dir: &str, lockfile: &Lockfile) {
+    let path = format!("{}/{}", modpack_dir, LOCKFILE_NAME);
+    files::write(&path, serde_json::to_string_pretty(lockfile).unwrap()).await;
+}
@@ -1,11 +1,17 @@
 #![feature(const_trait_impl)]
 
+use futures::stream::{self, StreamExt};
 use once_cell::sync::Lazy;
 use serde::Deserialize;
 use tokio::process::Command;
 
 mod files;
+mod interop;
+mod lockfile;
 mod modrinth;
+mod sources;
+
+use lockfile::{LockedMod, Lockfile};
 
 #[derive(Clone, Deserialize)]
 pub struct ModPackDescriptor {
@@ -16,20 +22,42 @@ pub struct ModPackDescriptor {
     target: String,
     fabric: String,
     mods: Vec<String>,
-    external: Vec<External>
+    external: Vec<External>,
+    #[serde(default = "default_concurrency")]
+    concurrency: u32,
+    #[serde(default = "default_loader_type")]
+    loader_type: String
+}
+
+/// Default number of mods downloaded at once, used when a descriptor omits `concurrency`.
+fn default_concurrency() -> u32 {
+    10
+}
+
+/// Default loader kind, used when a descriptor omits `loader_type`.
+fn default_loader_type() -> String {
+    "fabric".to_string()
 }
 
 #[derive(Clone, Deserialize)]
 pub struct External {
     url: String,
     file: String,
-    extract: Option<String>
+    extract: Option<String>,
+    /// The expected digest, if the source reported one (an mrpack's `hashes` or a packwiz
+    /// `.pw.toml`'s `hash`), so `download_external` can verify instead of trusting it blind.
+    #[serde(default)]
+    hash: Option<String>,
+    /// Which algorithm `hash` was computed with (`sha1`, `sha256`, or `sha512`).
+    #[serde(default, rename = "hashAlgo")]
+    hash_algo: Option<String>
 }
 
 #[derive(Clone)]
 pub struct Target {
     file_path: String,
-    target_version: String
+    target_version: String,
+    loader_type: String
 }
 
 // Create a global variable for the reqwest client.
@@ -38,37 +66,48 @@ pub static DEFAULT_AGENT: Lazy<String> = Lazy::new(|| "Magix-Archive/modpack-ins
 
 #[tokio::main]
 async fn main() {
-    // Check if the '-server' argument was passed.
+    // Check if the '-server' argument was passed, and whether we're importing a third-party pack.
     let mut server = false;
-    for arg in std::env::args() {
+    let mut mrpack_path: Option<String> = None;
+    let mut packwiz_url: Option<String> = None;
+    let args = std::env::args().collect::<Vec<String>>();
+    for (i, arg) in args.iter().enumerate() {
         if arg == "-server" {
             server = true;
+        } else if arg == "-mrpack" {
+            mrpack_path = args.get(i + 1).cloned();
+        } else if arg == "-packwiz" {
+            packwiz_url = args.get(i + 1).cloned();
         }
     }
 
-    // Check if the modpack file exists.
-    if !files::exists("modpack.json").await {
-        // Check if the running executable is a URL.
-        let mut exe_path = std::env::current_exe().unwrap().to_str().unwrap().to_string();
-        // Remove the '.exe' and path from the executable name.
-        exe_path = exe_path.replace(".exe", "");
-        exe_path = exe_path.split("\\").collect::<Vec<&str>>().last().unwrap().to_string();
-        exe_path = exe_path.replace("-", "/");
-        exe_path = exe_path.replace(";", ":");
-        if files::is_url(exe_path.clone()) {
-            // Download the modpack file.
-            files::download(exe_path, "modpack.json".to_string())
-                .await.expect("Unable to download modpack file.");
-        } else {
-            println!("Modpack file not found.");
-            println!("{}", exe_path.clone());
-            return;
+    // Resolve the modpack descriptor, either from a third-party format or the native JSON file.
+    let decoded = if let Some(path) = mrpack_path.clone() {
+        interop::mrpack::load(&path)
+    } else if let Some(url) = packwiz_url {
+        interop::packwiz::load(&url).await
+    } else {
+        // Check if the modpack file exists.
+        if !files::exists("modpack.json").await {
+            // Check if the running executable's name encodes a URL.
+            let exe_path = std::env::current_exe().unwrap();
+            let exe_name = exe_path.file_stem().unwrap().to_str().unwrap().to_string();
+            let url_guess = exe_name.replace("-", "/").replace(";", ":");
+            if files::is_url(url_guess.clone()) {
+                // Download the modpack file.
+                files::download(url_guess, "modpack.json".to_string())
+                    .await.expect("Unable to download modpack file.");
+            } else {
+                println!("Modpack file not found.");
+                println!("{}", url_guess.clone());
+                return;
+            }
         }
-    }
 
-    // Read the modpack data file.
-    let file = files::read("modpack.json").await;
-    let decoded = serde_json::from_str::<ModPackDescriptor>(&file).unwrap();
+        // Read the modpack data file.
+        let file = files::read("modpack.json").await;
+        serde_json::from_str::<ModPackDescriptor>(&file).unwrap()
+    };
     let modpack = decoded.clone();
 
     // Get the current directory.
@@ -78,23 +117,27 @@ async fn main() {
 
     if !server {
         // Check if Minecraft is installed.
-        let app_data = files::get_appdata().unwrap();
-        let versions_dir = format!("{}/{}/{}",
-                                   app_data.to_str().unwrap(),
-                                   ".minecraft", "versions");
-        target_dir = versions_dir.clone();
+        let versions_dir = files::get_minecraft_dir().join("versions");
+        target_dir = versions_dir.to_str().unwrap().to_string();
 
-        let loader = format!("{}/{}", versions_dir, decoded.loader);
+        let loader = format!("{}/{}", target_dir, decoded.loader);
         if !files::exists(&loader).await {
             download_loader(modpack.clone()).await;
         }
     }
 
-    // Check if the modpack is already installed.
+    // Check if the modpack is already installed; if so, reconcile it against its lockfile
+    // instead of treating the folder's mere existence as "done".
     let modpack_dir = format!("{}/{}", target_dir, decoded.folder);
     if files::exists(&modpack_dir).await {
-        // TODO: Update the modpack.
-        println!("Modpack already installed.");
+        println!("Updating modpack {} v{}...", decoded.name, decoded.version);
+        update_modpack(modpack_dir.clone(), decoded, server).await;
+
+        if !server {
+            create_profile(modpack_dir, modpack).await;
+        }
+
+        println!("Modpack updated.");
         return;
     }
 
@@ -108,117 +151,225 @@ async fn main() {
     files::create_dir(&format!("{}/{}", modpack_dir.clone(), "mods")).await;
     // Create the 'config' directory.
     files::create_dir(&format!("{}/{}", modpack_dir.clone(), "config")).await;
+
+    // Extract the '.mrpack' overrides into the instance, if importing from one.
+    if let Some(path) = mrpack_path {
+        interop::mrpack::extract_overrides(&path, &modpack_dir);
+    }
+
     // Create the target object.
     let target = Target {
         file_path: modpack_dir.clone(),
-        target_version: decoded.target.clone()
+        target_version: decoded.target.clone(),
+        loader_type: decoded.loader_type.clone()
     };
 
-    // Split the mods needed to download into 5 groups.
-    let mods = decoded.mods.clone();
-    let mut mods_1 = Vec::new();
-    let mut mods_2 = Vec::new();
-    let mut mods_3 = Vec::new();
-    let mut mods_4 = Vec::new();
-    let mut mods_5 = Vec::new();
-    for (i, _mod) in mods.iter().enumerate() {
-        if i % 5 == 0 {
-            mods_1.push(_mod.clone());
-        } else if i % 5 == 1 {
-            mods_2.push(_mod.clone());
-        } else if i % 5 == 2 {
-            mods_3.push(_mod.clone());
-        } else if i % 5 == 3 {
-            mods_4.push(_mod.clone());
-        } else if i % 5 == 4 {
-            mods_5.push(_mod.clone());
-        }
+    // Download the mods, bounded by the descriptor's concurrency limit, recording which
+    // files each entry resolved to for the lockfile.
+    let mut lock = Lockfile {
+        mods: download_mods(target.clone(), decoded.mods.clone(), server, decoded.concurrency).await,
+        external: Vec::new()
+    };
+
+    // Download the external mods.
+    for external in decoded.external {
+        lock.external.push(download_external(&modpack_dir, external).await);
     }
 
-    // Create 5 workers to download the mods.
-    let mut workers = Vec::new();
-    let target_w1 = target.clone();
-    let target_w2 = target.clone();
-    let target_w3 = target.clone();
-    let target_w4 = target.clone();
-    let target_w5 = target.clone();
-    // Download the mods.
-    workers.push(tokio::spawn(async move {
-        for _mod in mods_1 {
-            modrinth::download(target_w1.clone(), _mod, server).await.unwrap();
-        }
-    }));
-    workers.push(tokio::spawn(async move {
-        for _mod in mods_2 {
-            modrinth::download(target_w2.clone(), _mod, server).await.unwrap();
-        }
-    }));
-    workers.push(tokio::spawn(async move {
-        for _mod in mods_3 {
-            modrinth::download(target_w3.clone(), _mod, server).await.unwrap();
-        }
-    }));
-    workers.push(tokio::spawn(async move {
-        for _mod in mods_4 {
-            modrinth::download(target_w4.clone(), _mod, server).await.unwrap();
-        }
-    }));
-    workers.push(tokio::spawn(async move {
-        for _mod in mods_5 {
-            modrinth::download(target_w5.clone(), _mod, server).await.unwrap();
-        }
-    }));
+    // Write the lockfile so a future run can reconcile instead of re-installing blindly.
+    lockfile::write(&modpack_dir, &lock).await;
 
-    // Wait for the workers to finish.
-    for worker in workers {
-        worker.await.unwrap_or_else(|error| {
+    // Create a Minecraft profile.
+    if !server {
+        create_profile(modpack_dir.clone(), modpack).await;
+    }
+
+    println!("Modpack installed.");
+}
+
+/// Downloads a single mod entry, returning a lockfile entry for every file it resolved
+/// (the entry itself, plus any dependencies Modrinth pulled in alongside it).
+async fn download_one(target: Target, _mod: String, server: bool) -> Vec<LockedMod> {
+    let resolved = sources::download(target.clone(), _mod.clone(), server).await
+        .unwrap_or_else(|error| {
             println!("Failed to download mod. {}", error);
+            vec![]
         });
+
+    let mut locked = Vec::new();
+    for (filename, version) in resolved {
+        let file_path = format!("mods/{}", filename);
+        let sha1 = files::sha1(&format!("{}/{}", target.file_path, file_path)).await;
+        locked.push(LockedMod { source_id: _mod.clone(), file_path, sha1: Some(sha1), version });
     }
 
-    // Download the external mods.
-    for external in decoded.external {
-        // Check if the file contains a path.
-        if external.file.contains("/") {
-            // Create the directory.
-            let path = format!("{}/{}", modpack_dir.clone(),
-                               external.file.split("/").collect::<Vec<&str>>()[0]);
-            files::create_dir(&path).await;
-        }
+    locked
+}
+
+/// Maximum number of download attempts for an `external` entry before a hash mismatch is given up on.
+const MAX_EXTERNAL_DOWNLOAD_ATTEMPTS: u32 = 3;
+
+/// Downloads a single `external` entry (and extracts it, if it's a ZIP archive configured
+/// with an `extract` destination), returning its lockfile entry keyed by URL since externals
+/// carry no other stable ID.
+async fn download_external(modpack_dir: &str, external: External) -> LockedMod {
+    // Check if the file contains a path.
+    if external.file.contains("/") {
+        // Create the directory.
+        let path = format!("{}/{}", modpack_dir,
+                           external.file.split("/").collect::<Vec<&str>>()[0]);
+        files::create_dir(&path).await;
+    }
+
+    let path = format!("{}/{}", modpack_dir, external.file);
 
-        let path = format!("{}/{}", modpack_dir.clone(), external.file);
-        files::download(external.url, path.clone()).await.unwrap_or_else(|_| {
+    // Download the file, retrying if it doesn't match the source-reported hash (when there
+    // is one) the same way `sources::save_all`/`modrinth::matches_hashes` do.
+    for attempt in 1..=MAX_EXTERNAL_DOWNLOAD_ATTEMPTS {
+        files::download(external.url.clone(), path.clone()).await.unwrap_or_else(|_| {
             println!("Failed to download {}.", external.file);
         });
 
-        println!("Downloaded {}.", external.file);
+        if files::matches_hash(&path, &external.hash, &external.hash_algo).await {
+            break;
+        }
 
-        // Check if the file is a ZIP archive.
-        if external.file.ends_with(".zip") &&
-            external.extract.is_some() {
-            // Extract the archive to the target destination.
-            let destination = format!("{}/{}", modpack_dir.clone(), external.extract.unwrap());
-            files::extract_archive(path.clone(), destination);
-            // Delete the archive.
-            files::delete(path.as_str()).await;
+        if attempt == MAX_EXTERNAL_DOWNLOAD_ATTEMPTS {
+            println!("Giving up on {} after {} failed attempts.", external.file, MAX_EXTERNAL_DOWNLOAD_ATTEMPTS);
+        } else {
+            println!("Hash mismatch for {} (attempt {}/{}); retrying.", external.file, attempt, MAX_EXTERNAL_DOWNLOAD_ATTEMPTS);
+            files::delete(&path).await;
+        }
+    }
 
-            println!("Extracted {}.", external.file);
+    println!("Downloaded {}.", external.file);
+
+    // Hash the downloaded archive before it's potentially extracted-and-deleted below, so the
+    // lockfile still records what was fetched.
+    let sha1 = files::sha1(&path).await;
+
+    // Check if the file is a ZIP archive.
+    if external.file.ends_with(".zip") && external.extract.is_some() {
+        // Extract the archive to the target destination.
+        let destination = format!("{}/{}", modpack_dir, external.extract.unwrap());
+        files::extract_archive(path.clone(), destination);
+        // Delete the archive.
+        files::delete(path.as_str()).await;
+
+        println!("Extracted {}.", external.file);
+    }
+
+    LockedMod { source_id: external.url, file_path: external.file, sha1: Some(sha1), version: None }
+}
+
+/// Downloads a set of mod entries, running at most `concurrency` downloads at once.
+async fn download_mods(target: Target, mods: Vec<String>, server: bool, concurrency: u32) -> Vec<LockedMod> {
+    stream::iter(mods)
+        .map(|_mod| {
+            let target = target.clone();
+            async move { download_one(target, _mod, server).await }
+        })
+        .buffer_unordered(concurrency as usize)
+        .collect::<Vec<Vec<LockedMod>>>()
+        .await
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Reconciles an already-installed modpack against its lockfile: downloads entries that were
+/// newly added to the descriptor, deletes entries that were removed from it, and re-resolves
+/// entries whose source now reports a different version than what's pinned in the lockfile.
+async fn update_modpack(modpack_dir: String, decoded: ModPackDescriptor, server: bool) {
+    let mut lock = lockfile::read(&modpack_dir).await;
+    let target = Target {
+        file_path: modpack_dir.clone(),
+        target_version: decoded.target.clone(),
+        loader_type: decoded.loader_type.clone()
+    };
+
+    // Remove mods that are no longer listed in the descriptor.
+    let mut kept = Vec::new();
+    for entry in lock.mods {
+        if decoded.mods.contains(&entry.source_id) {
+            kept.push(entry);
+        } else {
+            println!("Removing {}.", entry.file_path);
+            files::delete(&format!("{}/{}", modpack_dir, entry.file_path)).await;
         }
     }
+    lock.mods = kept;
 
-    // Create a Minecraft profile.
-    if !server {
-        create_profile(modpack_dir.clone(), modpack).await;
+    // Re-resolve mods that are still listed, in case their source now points at a newer
+    // version than what's pinned (e.g. a `modrinth:` entry with no version suffix always
+    // tracks latest). A resolution that comes back with a different version than every kept
+    // file for that source ID is stale and gets replaced.
+    let mut reresolved = Vec::new();
+    for source_id in decoded.mods.iter().cloned().collect::<std::collections::HashSet<String>>() {
+        let pinned: Vec<LockedMod> = lock.mods.iter()
+            .filter(|entry| entry.source_id == source_id)
+            .cloned()
+            .collect();
+        if pinned.is_empty() {
+            continue;
+        }
+
+        let fresh = download_one(target.clone(), source_id.clone(), server).await;
+        let changed = fresh.iter().any(|entry| {
+            !pinned.iter().any(|old| old.file_path == entry.file_path && old.version == entry.version)
+        });
+
+        if changed {
+            println!("{} resolved to a new version; replacing.", source_id);
+            for old in &pinned {
+                // A file the fresh resolution wrote to the same path is the replacement itself.
+                if fresh.iter().any(|entry| entry.file_path == old.file_path) {
+                    continue;
+                }
+                files::delete(&format!("{}/{}", modpack_dir, old.file_path)).await;
+            }
+            reresolved.extend(fresh);
+        } else {
+            reresolved.extend(pinned);
+        }
     }
+    lock.mods = reresolved;
 
-    println!("Modpack installed.");
+    // Download mods that are newly listed in the descriptor.
+    let previous_ids: Vec<String> = lock.mods.iter().map(|entry| entry.source_id.clone()).collect();
+    let added: Vec<String> = decoded.mods.into_iter()
+        .filter(|id| !previous_ids.contains(id))
+        .collect();
+    lock.mods.extend(download_mods(target, added, server, decoded.concurrency).await);
+
+    // Reconcile `external` entries the same way: remove ones no longer listed, re-download
+    // ones newly added. Externals have no per-source version to compare, so they're only
+    // replaced when removed-then-re-added.
+    let mut kept_external = Vec::new();
+    for entry in lock.external {
+        if decoded.external.iter().any(|external| external.url == entry.source_id) {
+            kept_external.push(entry);
+        } else {
+            println!("Removing {}.", entry.file_path);
+            files::delete(&format!("{}/{}", modpack_dir, entry.file_path)).await;
+        }
+    }
+    lock.external = kept_external;
+
+    let previous_urls: Vec<String> = lock.external.iter().map(|entry| entry.source_id.clone()).collect();
+    for external in decoded.external {
+        if !previous_urls.contains(&external.url) {
+            lock.external.push(download_external(&modpack_dir, external).await);
+        }
+    }
+
+    lockfile::write(&modpack_dir, &lock).await;
 }
 
 /// Creates a Minecraft profile.
 async fn create_profile(modpack_dir: String, modpack: ModPackDescriptor) {
     // Get the .minecraft directory.
-    let app_data = files::get_appdata().unwrap();
-    let minecraft_dir = format!("{}/{}", app_data.to_str().unwrap(), ".minecraft");
+    let minecraft_dir = files::get_minecraft_dir().to_str().unwrap().to_string();
 
     // Read the JSON file.
     let file = files::read(&format!("{}/{}", minecraft_dir.clone(), "launcher_profiles.json")).await;
@@ -241,21 +392,65 @@ async fn create_profile(modpack_dir: String, modpack: ModPackDescriptor) {
                  decoded.to_string()).await;
 }
 
-/// Download and installs the Fabric loader.
+/// Downloads and installs the mod loader specified by the descriptor's `loader_type`.
 async fn download_loader(modpack: ModPackDescriptor) {
-    // Get the %tmp% directory.
-    let tmp_dir = files::get_temp().unwrap();
-    // Download the Fabric Loader to the %tmp% directory.
+    match modpack.loader_type.as_str() {
+        "quilt" => install_quilt(&modpack).await,
+        "forge" => install_forge(&modpack, "https://maven.minecraftforge.net/net/minecraftforge/forge").await,
+        "neoforge" => install_forge(&modpack, "https://maven.neoforged.net/releases/net/neoforged/neoforge").await,
+        _ => install_fabric(&modpack).await
+    }
+}
+
+/// Downloads and installs the Fabric loader.
+async fn install_fabric(modpack: &ModPackDescriptor) {
+    // Get the temporary directory.
+    let installer_path = files::get_temp().join("fabric-installer.jar");
+    // Download the Fabric Loader to the temporary directory.
     files::download("https://maven.fabricmc.net/net/fabricmc/fabric-installer/0.11.2/fabric-installer-0.11.2.jar".to_string(),
-                    format!("{}/{}", tmp_dir.to_str().unwrap(), "fabric-installer.jar")).await.unwrap();
+                    installer_path.to_str().unwrap().to_string()).await.unwrap();
     // Run the Fabric Installer.
     Command::new("java")
         .arg("-jar")
-        .arg(format!("{}/{}", tmp_dir.to_str().unwrap(), "fabric-installer.jar"))
+        .arg(installer_path.to_str().unwrap())
         .arg("client")
         .arg("-loader")
-        .arg(modpack.fabric)
+        .arg(&modpack.fabric)
         .arg("-mcversion")
-        .arg(modpack.target)
+        .arg(&modpack.target)
+        .spawn().unwrap().wait().await.unwrap();
+}
+
+/// Downloads and installs the Quilt loader, whose installer is largely Fabric-compatible.
+async fn install_quilt(modpack: &ModPackDescriptor) {
+    let installer_path = files::get_temp().join("quilt-installer.jar");
+    files::download("https://maven.quiltmc.org/repository/release/org/quiltmc/quilt-installer/0.9.1/quilt-installer-0.9.1.jar".to_string(),
+                    installer_path.to_str().unwrap().to_string()).await.unwrap();
+    // Run the Quilt Installer.
+    Command::new("java")
+        .arg("-jar")
+        .arg(installer_path.to_str().unwrap())
+        .arg("install")
+        .arg("client")
+        .arg(&modpack.target)
+        .arg(&modpack.fabric)
+        .spawn().unwrap().wait().await.unwrap();
+}
+
+/// Downloads and installs a Forge/NeoForge loader from its Maven coordinates, then runs its
+/// installer headlessly. `modpack.fabric` holds the `<mcversion>-<loaderversion>` id both
+/// projects use in their installer jar names.
+async fn install_forge(modpack: &ModPackDescriptor, maven_base: &str) {
+    let installer_name = format!("{}-installer.jar", modpack.fabric);
+    let url = format!("{}/{}/{}", maven_base, modpack.fabric, installer_name);
+    let installer_path = files::get_temp().join(&installer_name);
+
+    files::download(url, installer_path.to_str().unwrap().to_string()).await.unwrap();
+
+    // Run the installer headlessly.
+    Command::new("java")
+        .arg("-jar")
+        .arg(installer_path.to_str().unwrap())
+        .arg("--installClient")
         .spawn().unwrap().wait().await.unwrap();
 }
\ No newline at end of file
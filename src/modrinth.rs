@@ -1,3 +1,5 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+
 use async_recursion::async_recursion;
 use reqwest::header::USER_AGENT;
 use serde::{Deserialize};
@@ -8,6 +10,10 @@ use crate::{CLIENT, DEFAULT_AGENT, files, Target};
 
 const MODRINTH_API: &str = "https://api.modrinth.com/v2";
 
+/// The `X-Ratelimit-Remaining` value seen on the last response, shared across every
+/// concurrent download so the pool paces itself before the API starts returning 429s.
+static RATE_LIMIT_REMAINING: AtomicI64 = AtomicI64::new(i64::MAX);
+
 #[derive(Clone, Deserialize)]
 pub struct ModrinthModInfo {
     id: String,
@@ -19,6 +25,7 @@ pub struct ModrinthModInfo {
 
 #[derive(Clone, Deserialize)]
 pub struct ModrinthModVersion {
+    id: String,
     project_id: Option<String>,
     files: Vec<ModrinthFile>,
     dependencies: Vec<ModrinthDependency>,
@@ -29,7 +36,14 @@ pub struct ModrinthModVersion {
 #[derive(Clone, Deserialize)]
 pub struct ModrinthFile {
     url: String,
-    filename: String
+    filename: String,
+    hashes: ModrinthHashes
+}
+
+#[derive(Clone, Deserialize)]
+pub struct ModrinthHashes {
+    sha1: String,
+    sha512: String
 }
 
 #[derive(Clone, Deserialize)]
@@ -51,13 +65,31 @@ fn version_info(_mod: ModrinthModInfo, version: String) -> String {
 }
 
 /// Performs a request to the Modrinth API.
-/// Handles the rate limit system implemented.
+/// Paces itself as `X-Ratelimit-Remaining` approaches zero, and handles an outright 429.
 #[async_recursion]
 async fn make_request(url: String) -> Result<String, reqwest::Error> {
+    // Atomically claim a slot from the shared remaining-request budget before sending. Under
+    // the concurrent download pool, several tasks can reach this call at once; a plain
+    // load-then-sleep check would let all of them read the same not-yet-depleted count and
+    // send anyway. `fetch_sub` hands each caller a distinct value (the count before its own
+    // decrement), so only callers whose decrement leaves the budget above zero send at once -
+    // the rest pause for 500ms before sending.
+    if RATE_LIMIT_REMAINING.fetch_sub(1, Ordering::SeqCst) <= 1 {
+        println!("Approaching the Modrinth rate limit; pacing requests...");
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+
     let response = CLIENT.get(url.clone())
         .header(USER_AGENT, DEFAULT_AGENT.clone())
         .send().await?;
 
+    // Refill the budget from the API's own bookkeeping, replacing our estimate with ground truth.
+    if let Some(remaining) = response.headers().get("X-Ratelimit-Remaining")
+        .and_then(|header| header.to_str().ok())
+        .and_then(|value| value.parse::<i64>().ok()) {
+        RATE_LIMIT_REMAINING.store(remaining, Ordering::SeqCst);
+    }
+
     // Check if the request was successful.
     if response.status().eq(&429) {
         // Get the 'X-Ratelimit-Reset' header.
@@ -77,9 +109,20 @@ async fn make_request(url: String) -> Result<String, reqwest::Error> {
     Ok(response.text().await.unwrap())
 }
 
+/// Checks whether a version's loader list satisfies the requested loader type.
+/// Quilt is largely Fabric-compatible, so a Quilt target also accepts Fabric-only jars.
+fn loader_matches(loader_type: &str, loaders: &[String]) -> bool {
+    if loader_type == "quilt" {
+        loaders.iter().any(|loader| loader == "quilt" || loader == "fabric")
+    } else {
+        loaders.iter().any(|loader| loader == loader_type)
+    }
+}
+
 /// Picks the correct version from the mod's versions.
-async fn pick_version(game_ver: String, mut _mod: ModrinthModInfo) -> ModrinthModVersion {
+async fn pick_version(game_ver: String, loader_type: String, mut _mod: ModrinthModInfo) -> ModrinthModVersion {
     let mut version = ModrinthModVersion {
+        id: String::new(),
         files: vec![], dependencies: vec![],
         game_versions: vec![], loaders: vec![],
         project_id: None
@@ -97,6 +140,7 @@ async fn pick_version(game_ver: String, mut _mod: ModrinthModInfo) -> ModrinthMo
         ).await.unwrap().as_str()).unwrap_or_else(|error| {
             println!("Unable to download {} ({}). Error: {}", _mod.clone().title, _mod.clone().id, error);
             ModrinthModVersion {
+                id: String::new(),
                 files: vec![], dependencies: vec![],
                 game_versions: vec![], loaders: vec![],
                 project_id: None
@@ -105,7 +149,7 @@ async fn pick_version(game_ver: String, mut _mod: ModrinthModInfo) -> ModrinthMo
 
         // Check if the version is compatible.
         if version.game_versions.contains(&game_ver) &&
-            version.loaders.contains(&"fabric".to_string()) {
+            loader_matches(&loader_type, &version.loaders) {
             break;
         }
     }
@@ -113,42 +157,67 @@ async fn pick_version(game_ver: String, mut _mod: ModrinthModInfo) -> ModrinthMo
     version
 }
 
+/// Maximum number of download attempts for a mod's file before a hash mismatch is given up on.
+const MAX_DOWNLOAD_ATTEMPTS: u32 = 3;
+
+/// Checks a downloaded file's SHA-1 and SHA-512 digests against the manifest's hashes.
+async fn matches_hashes(path: &str, hashes: &ModrinthHashes) -> bool {
+    files::sha1(path).await == hashes.sha1 && files::sha512(path).await == hashes.sha512
+}
+
 /// Saves the mod's version to the file system.
-async fn save_version(target: Target, version: ModrinthModVersion, _mod: ModrinthModInfo) -> Result<(), reqwest::Error> {
+/// Returns the name of the file it saved and the resolved version's ID, or `None` if the
+/// mod has no files or every download attempt failed the hash check.
+async fn save_version(target: Target, version: ModrinthModVersion, _mod: ModrinthModInfo) -> Result<Option<(String, Option<String>)>, reqwest::Error> {
     // Check if the mod doesn't exist.
     if version.files.len() < 1 {
         println!("Skipped {} ({}).", _mod.title, version.project_id.unwrap_or("".to_string()));
-        return Ok(());
+        return Ok(None);
     }
 
-    // Get the URL & file name for the mod.
-    let url = &version.files[0].url;
-    let file_name = &version.files[0].filename;
+    // Get the URL, file name & hashes for the mod.
+    let file = &version.files[0];
+    let url = &file.url;
+    let hashes = &file.hashes;
     // URL decode the file name.
-    let file_name = percent_encoding::percent_decode_str(file_name)
+    let file_name = percent_encoding::percent_decode_str(&file.filename)
         .decode_utf8().unwrap();
     let path = format!("{}/mods/{}", target.clone().file_path, file_name);
+    let version_id = Some(version.id.clone());
 
-    // Check if the file already exists.
-    if files::exists(path.clone().as_str()).await {
-        return Ok(());
+    // Check if the file already exists and still matches the manifest's hashes.
+    if files::exists(path.as_str()).await {
+        if matches_hashes(&path, hashes).await {
+            return Ok(Some((file_name.to_string(), version_id)));
+        }
+        println!("{} doesn't match the expected hash; re-downloading.", file_name);
     }
 
-    // Download the mod.
-    let bytes = CLIENT.get(url)
-        .header(USER_AGENT, DEFAULT_AGENT.clone())
-        .send().await?.bytes().await?;
-    // Save the mod to the target destination.
-    fs::write(path, bytes).await
-        .expect("Failed to save mod.");
+    // Download the mod, retrying if the downloaded bytes don't match the manifest's hashes.
+    for attempt in 1..=MAX_DOWNLOAD_ATTEMPTS {
+        let bytes = CLIENT.get(url)
+            .header(USER_AGENT, DEFAULT_AGENT.clone())
+            .send().await?.bytes().await?;
+        // Save the mod to the target destination.
+        fs::write(&path, bytes).await
+            .expect("Failed to save mod.");
+
+        if matches_hashes(&path, hashes).await {
+            println!("Downloaded {} ({}).", _mod.title, version.project_id.unwrap_or("".to_string()));
+            return Ok(Some((file_name.to_string(), version_id)));
+        }
+
+        println!("Hash mismatch for {} (attempt {}/{}); retrying.", file_name, attempt, MAX_DOWNLOAD_ATTEMPTS);
+        files::delete(&path).await;
+    }
 
-    println!("Downloaded {} ({}).", _mod.title, version.project_id.unwrap_or("".to_string()));
-    Ok(())
+    println!("Giving up on {} after {} failed attempts.", file_name, MAX_DOWNLOAD_ATTEMPTS);
+    Ok(None)
 }
 
 /// Attempts to download a mod from Modrinth.
 /// No checks are performed.
-async fn download_unsafe(target: Target, _mod: String) -> Result<(), reqwest::Error> {
+async fn download_unsafe(target: Target, _mod: String) -> Result<Option<(String, Option<String>)>, reqwest::Error> {
     // Get the mod's info.
     let mod_info: ModrinthModInfo = serde_json::from_str(make_request(
         mod_info(_mod.clone())
@@ -156,15 +225,17 @@ async fn download_unsafe(target: Target, _mod: String) -> Result<(), reqwest::Er
 
     // Get the matching version.
     let version_info = pick_version(
-        target.clone().target_version, mod_info.clone()).await;
+        target.clone().target_version, target.clone().loader_type, mod_info.clone()).await;
 
     // Save the version to the file system.
-    Ok(save_version(target.clone(), version_info, mod_info).await?)
+    save_version(target.clone(), version_info, mod_info).await
 }
 
 /// Downloads a mod from Modrinth.
 /// Checks for dependencies.
-pub async fn download(target: Target, _mod: String, is_server: bool) -> Result<bool, reqwest::Error> {
+/// Returns the name and resolved version of every file it saved, including any resolved
+/// dependencies.
+pub async fn download(target: Target, _mod: String, is_server: bool) -> Result<Vec<(String, Option<String>)>, reqwest::Error> {
     // Get the mod's info.
     let mod_info: ModrinthModInfo = serde_json::from_str(make_request(
         mod_info(_mod.clone())
@@ -172,19 +243,21 @@ pub async fn download(target: Target, _mod: String, is_server: bool) -> Result<b
 
     // Get the matching version.
     let version_info = pick_version(
-        target.clone().target_version, mod_info.clone()).await;
+        target.clone().target_version, target.clone().loader_type, mod_info.clone()).await;
 
     // Check if the mod is supported.
     if is_server {
         if mod_info.server_side == "unsupported" {
-            return Ok(false);
+            return Ok(vec![]);
         }
     } else {
         if mod_info.client_side == "unsupported" {
-            return Ok(false);
+            return Ok(vec![]);
         }
     }
 
+    let mut saved = Vec::new();
+
     // Check if other mods are required.
     let dependencies = version_info.clone().dependencies;
     if dependencies.len() > 0 {
@@ -192,11 +265,17 @@ pub async fn download(target: Target, _mod: String, is_server: bool) -> Result<b
         for dependency in dependencies {
             // Check if the dependency is a mod.
             if dependency.dependency_type == "required_mod" {
-                download_unsafe(target.clone(), dependency.project_id).await?;
+                if let Some(saved_file) = download_unsafe(target.clone(), dependency.project_id).await? {
+                    saved.push(saved_file);
+                }
             }
         }
     }
 
     // Save the version to the file system.
-    Ok(save_version(target.clone(), version_info, mod_info).await.is_ok())
+    if let Some(saved_file) = save_version(target.clone(), version_info, mod_info).await? {
+        saved.push(saved_file);
+    }
+
+    Ok(saved)
 }
\ No newline at end of file
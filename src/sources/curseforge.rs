@@ -0,0 +1,67 @@
+use reqwest::header::USER_AGENT;
+use serde::Deserialize;
+
+use crate::{CLIENT, DEFAULT_AGENT, Target};
+use crate::sources::{ModSource, ResolvedFile};
+
+const CURSEFORGE_API: &str = "https://api.curseforge.com/v1";
+
+#[derive(Deserialize)]
+struct CurseForgeFilesResponse {
+    data: Vec<CurseForgeFile>
+}
+
+#[derive(Deserialize)]
+struct CurseForgeFile {
+    id: u32,
+    #[serde(rename = "downloadUrl")]
+    download_url: Option<String>,
+    #[serde(rename = "fileName")]
+    file_name: String,
+    #[serde(rename = "gameVersions")]
+    game_versions: Vec<String>,
+    hashes: Vec<CurseForgeFileHash>
+}
+
+#[derive(Deserialize)]
+struct CurseForgeFileHash {
+    value: String,
+    algo: u32
+}
+
+/// CurseForge's `HashAlgo` enum value for a SHA-1 digest.
+const HASH_ALGO_SHA1: u32 = 1;
+
+pub struct CurseForgeSource;
+
+#[async_trait::async_trait]
+impl ModSource for CurseForgeSource {
+    /// Resolves a CurseForge mod ID to the newest file matching the target game version.
+    /// Requires a `CURSEFORGE_API_KEY` environment variable, as the CurseForge API mandates one.
+    async fn resolve(&self, target: &Target, id: &str, _is_server: bool) -> Result<Vec<ResolvedFile>, reqwest::Error> {
+        let api_key = std::env::var("CURSEFORGE_API_KEY").unwrap_or_default();
+        let url = format!("{}/mods/{}/files", CURSEFORGE_API, id);
+
+        let response: CurseForgeFilesResponse = CLIENT.get(url)
+            .header(USER_AGENT, DEFAULT_AGENT.clone())
+            .header("x-api-key", api_key)
+            .send().await?.json().await?;
+
+        // CurseForge's file IDs are assigned sequentially, so the highest one among the
+        // matches is the newest upload; the API gives no ordering guarantee to rely on.
+        let matching = response.data.into_iter()
+            .filter(|file| file.game_versions.iter().any(|version| version == &target.target_version))
+            .max_by_key(|file| file.id);
+
+        Ok(match matching {
+            Some(CurseForgeFile { download_url: Some(url), file_name, id, hashes, .. }) => {
+                let hash = hashes.into_iter()
+                    .find(|hash| hash.algo == HASH_ALGO_SHA1)
+                    .map(|hash| hash.value);
+                // CurseForge has no user-facing version string on a file, only its own file ID.
+                vec![ResolvedFile { url, filename: file_name, hash, version: Some(id.to_string()) }]
+            },
+            _ => vec![]
+        })
+    }
+}
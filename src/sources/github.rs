@@ -0,0 +1,70 @@
+use reqwest::header::USER_AGENT;
+use serde::Deserialize;
+
+use crate::{CLIENT, DEFAULT_AGENT, Target};
+use crate::sources::{ModSource, ResolvedFile};
+
+const GITHUB_API: &str = "https://api.github.com";
+
+#[derive(Deserialize)]
+struct GitHubRelease {
+    tag_name: String,
+    assets: Vec<GitHubAsset>
+}
+
+#[derive(Deserialize)]
+struct GitHubAsset {
+    name: String,
+    browser_download_url: String
+}
+
+pub struct GitHubSource;
+
+#[async_trait::async_trait]
+impl ModSource for GitHubSource {
+    /// Resolves a `owner/repo@tag` (or `owner/repo` for the latest release) identifier
+    /// to the single release asset whose filename best matches the glob, defaulting to
+    /// `*.jar`. A glob can be appended with a trailing `:`, e.g. `owner/repo@tag:*-fabric.jar`.
+    /// When several assets match (a release jar alongside `-sources`/`-dev` variants), the
+    /// shortest matching name wins, since extra suffixes only make a name longer.
+    async fn resolve(&self, _target: &Target, id: &str, _is_server: bool) -> Result<Vec<ResolvedFile>, reqwest::Error> {
+        let (repo_and_tag, glob) = match id.split_once(':') {
+            Some((repo_and_tag, glob)) => (repo_and_tag, glob),
+            None => (id, "*.jar")
+        };
+        let (repo, tag) = repo_and_tag.split_once('@').unwrap_or((repo_and_tag, ""));
+
+        let url = if tag.is_empty() {
+            format!("{}/repos/{}/releases/latest", GITHUB_API, repo)
+        } else {
+            format!("{}/repos/{}/releases/tags/{}", GITHUB_API, repo, tag)
+        };
+
+        let release: GitHubRelease = CLIENT.get(url)
+            .header(USER_AGENT, DEFAULT_AGENT.clone())
+            .send().await?.json().await?;
+        let tag_name = release.tag_name.clone();
+
+        let picked = release.assets.into_iter()
+            .filter(|asset| matches_glob(glob, &asset.name))
+            .min_by_key(|asset| asset.name.len());
+
+        // GitHub releases don't carry a checksum; trust the asset once downloaded.
+        Ok(picked.into_iter()
+            .map(|asset| ResolvedFile {
+                url: asset.browser_download_url,
+                filename: asset.name,
+                hash: None,
+                version: Some(tag_name.clone())
+            })
+            .collect())
+    }
+}
+
+/// Matches a filename against a single-wildcard glob, e.g. `*-fabric.jar`.
+fn matches_glob(glob: &str, name: &str) -> bool {
+    match glob.split_once('*') {
+        Some((prefix, suffix)) => name.starts_with(prefix) && name.ends_with(suffix),
+        None => glob == name
+    }
+}
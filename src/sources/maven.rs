@@ -0,0 +1,33 @@
+use crate::Target;
+use crate::sources::{ModSource, ResolvedFile};
+
+const MAVEN_CENTRAL: &str = "https://repo1.maven.org/maven2";
+
+pub struct MavenSource;
+
+#[async_trait::async_trait]
+impl ModSource for MavenSource {
+    /// Resolves a `group:artifact:version` Maven coordinate to its JAR, downloaded from
+    /// Maven Central by default. A mod author's own repo can be used instead by appending
+    /// `@<repo-base>`, e.g. `net.fabricmc:fabric-api:0.x@https://maven.fabricmc.net`.
+    async fn resolve(&self, _target: &Target, id: &str, _is_server: bool) -> Result<Vec<ResolvedFile>, reqwest::Error> {
+        let (coordinate, repo_base) = match id.split_once('@') {
+            Some((coordinate, repo_base)) => (coordinate, repo_base),
+            None => (id, MAVEN_CENTRAL)
+        };
+
+        let parts: Vec<&str> = coordinate.split(':').collect();
+        if parts.len() != 3 {
+            println!("Invalid Maven coordinate: {}", id);
+            return Ok(vec![]);
+        }
+
+        let (group, artifact, version) = (parts[0], parts[1], parts[2]);
+        let group_path = group.replace('.', "/");
+        let filename = format!("{}-{}.jar", artifact, version);
+        let url = format!("{}/{}/{}/{}/{}", repo_base, group_path, artifact, version, filename);
+
+        // Maven Central doesn't hand back a checksum inline; trust the jar once downloaded.
+        Ok(vec![ResolvedFile { url, filename, hash: None, version: Some(version.to_string()) }])
+    }
+}
@@ -0,0 +1,99 @@
+use crate::{files, Target};
+
+pub mod curseforge;
+pub mod github;
+pub mod maven;
+
+/// A file resolved from a mod source, ready to be downloaded into the instance.
+pub struct ResolvedFile {
+    pub url: String,
+    pub filename: String,
+    /// SHA-1 digest the source reported for this file, if it gave one, so `save_all` can
+    /// verify the download instead of trusting a partial/rate-limited response.
+    pub hash: Option<String>,
+    /// The source's own version/file identifier, recorded in the lockfile so a later run
+    /// can tell a version bump apart from an untouched entry.
+    pub version: Option<String>
+}
+
+/// A backend capable of resolving a mod identifier into downloadable files.
+#[async_trait::async_trait]
+pub trait ModSource {
+    async fn resolve(&self, target: &Target, id: &str, is_server: bool) -> Result<Vec<ResolvedFile>, reqwest::Error>;
+}
+
+/// Downloads a mod entry, dispatching on its scheme prefix (`curseforge:`, `github:`, `maven:`).
+/// Entries without a recognized scheme (or prefixed `modrinth:`) fall back to Modrinth,
+/// which keeps its own dependency resolution and client/server support checks.
+/// Returns the name and resolved version (if known) of every file it saved.
+pub async fn download(target: Target, entry: String, is_server: bool) -> Result<Vec<(String, Option<String>)>, reqwest::Error> {
+    if let Some(id) = entry.strip_prefix("curseforge:") {
+        let resolved = curseforge::CurseForgeSource.resolve(&target, id, is_server).await?;
+        return save_all(&target, resolved).await;
+    }
+    if let Some(id) = entry.strip_prefix("github:") {
+        let resolved = github::GitHubSource.resolve(&target, id, is_server).await?;
+        return save_all(&target, resolved).await;
+    }
+    if let Some(id) = entry.strip_prefix("maven:") {
+        let resolved = maven::MavenSource.resolve(&target, id, is_server).await?;
+        return save_all(&target, resolved).await;
+    }
+
+    let id = entry.strip_prefix("modrinth:").unwrap_or(&entry).to_string();
+    crate::modrinth::download(target, id, is_server).await
+}
+
+/// Maximum number of download attempts for a resolved file before a hash mismatch is given up on.
+const MAX_DOWNLOAD_ATTEMPTS: u32 = 3;
+
+/// Saves every resolved file to the target's `mods` directory. A file already present is
+/// re-verified against the source's hash (when it gave one) instead of trusted on sight;
+/// a missing or mismatched file is downloaded, with up to `MAX_DOWNLOAD_ATTEMPTS` attempts in all.
+/// Returns the name and resolved version of every file saved.
+async fn save_all(target: &Target, resolved: Vec<ResolvedFile>) -> Result<Vec<(String, Option<String>)>, reqwest::Error> {
+    let mut saved = Vec::new();
+
+    for file in resolved {
+        let path = format!("{}/mods/{}", target.file_path, file.filename);
+
+        if files::exists(&path).await {
+            if matches_hash(&path, &file.hash).await {
+                saved.push((file.filename, file.version));
+                continue;
+            }
+            println!("{} doesn't match the expected hash; re-downloading.", file.filename);
+        }
+
+        let mut verified = false;
+        for attempt in 1..=MAX_DOWNLOAD_ATTEMPTS {
+            files::download(file.url.clone(), path.clone()).await?;
+
+            if matches_hash(&path, &file.hash).await {
+                verified = true;
+                break;
+            }
+
+            println!("Hash mismatch for {} (attempt {}/{}); retrying.", file.filename, attempt, MAX_DOWNLOAD_ATTEMPTS);
+            files::delete(&path).await;
+        }
+
+        if !verified {
+            println!("Giving up on {} after {} failed attempts.", file.filename, MAX_DOWNLOAD_ATTEMPTS);
+            continue;
+        }
+
+        saved.push((file.filename, file.version));
+    }
+
+    Ok(saved)
+}
+
+/// Checks a file's SHA-1 digest against the source's reported hash. Sources that don't
+/// report one (GitHub releases, raw Maven coordinates) are trusted once downloaded.
+async fn matches_hash(path: &str, hash: &Option<String>) -> bool {
+    match hash {
+        Some(expected) => &files::sha1(path).await == expected,
+        None => true
+    }
+}